@@ -1,15 +1,18 @@
-use std::cmp::Ordering;
+use std::cmp::{min, Ordering};
 use std::collections::HashMap;
-use std::convert::{TryFrom, TryInto};
+use std::convert::TryFrom;
 use std::fmt;
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{format_err, Context, Result};
+use chrono::{Datelike, TimeZone, Utc};
 use futures::future::join_all;
 use itertools::Itertools;
-use log::{debug, error, info};
 use maplit::hashmap;
 use structopt::StructOpt;
+use tracing::{debug, error, info, info_span, Instrument};
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -29,9 +32,53 @@ struct Args {
     #[structopt(long, default_value = "500")]
     serial_timeout_ms: u64,
 
-    /// Interval to refresh METAR data in seconds
+    /// Interval to wake and process results (retrying failed stations) in
+    /// seconds
+    #[structopt(long, default_value = "30")]
+    search_interval_s: u64,
+
+    /// Interval before a healthy station is re-queried from NOAA in seconds
     #[structopt(long, default_value = "300")]
-    refresh_interval_s: u64,
+    station_refetch_interval_s: u64,
+
+    /// Maximum age of a METAR observation in minutes before it is considered
+    /// stale and reported as such instead of its flight category
+    #[structopt(long, default_value = "90")]
+    metar_max_age_min: i64,
+
+    /// Address to serve the status/dashboard endpoint on (e.g. 127.0.0.1:8080)
+    #[structopt(long)]
+    http_addr: Option<SocketAddr>,
+
+    /// Proxy URL to route METAR requests through (e.g. http://proxy:3128)
+    #[structopt(long)]
+    proxy_url: Option<String>,
+
+    /// Username for proxy authentication
+    #[structopt(long)]
+    proxy_username: Option<String>,
+
+    /// Password for proxy authentication
+    #[structopt(long)]
+    proxy_password: Option<String>,
+
+    /// Log output format: "text" for human-readable or "json" for
+    /// machine-readable structured logs
+    #[structopt(long, default_value = "text")]
+    log_format: String,
+
+    /// Ceiling below which conditions are LIFR, in hundreds of feet
+    #[structopt(long, default_value = "5")]
+    ceiling_lifr_hundreds_ft: u32,
+
+    /// Ceiling below which conditions are IFR, in hundreds of feet
+    #[structopt(long, default_value = "10")]
+    ceiling_ifr_hundreds_ft: u32,
+
+    /// Ceiling at or below which conditions are marginal VFR, in hundreds of
+    /// feet
+    #[structopt(long, default_value = "30")]
+    ceiling_mvfr_hundreds_ft: u32,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -85,68 +132,86 @@ impl TryFrom<&metar::Visibility> for FlightRules {
     }
 }
 
-impl From<&Vec<metar::CloudLayer>> for FlightRules {
-    fn from(layers: &Vec<metar::CloudLayer>) -> Self {
-        if layers.is_empty() {
-            return FlightRules::Vfr;
+#[derive(Clone, Copy, Debug)]
+struct CeilingThresholds {
+    lifr: u32,
+    ifr: u32,
+    mvfr: u32,
+}
+
+impl CeilingThresholds {
+    fn classify(&self, hundreds_ft: u32) -> FlightRules {
+        if hundreds_ft < self.lifr {
+            FlightRules::LowIfr
+        } else if hundreds_ft < self.ifr {
+            FlightRules::Ifr
+        } else if hundreds_ft <= self.mvfr {
+            FlightRules::MarginalVfr
+        } else {
+            FlightRules::Vfr
+        }
+    }
+}
+
+impl FlightRules {
+    fn worse(self, other: FlightRules) -> FlightRules {
+        if self < other {
+            self
+        } else {
+            other
         }
+    }
 
-        let mut ceiling_altitudes = Vec::new();
+    fn from_cloud_layers(layers: &[metar::CloudLayer], thresholds: &CeilingThresholds) -> Self {
+        let mut rules: Option<FlightRules> = None;
 
         for layer in layers.iter() {
-            // TODO(jsvana): handle ceilings with unspecified altitudes
-            if let metar::CloudLayer::Broken(_, Some(altitude))
-            | metar::CloudLayer::Overcast(_, Some(altitude)) = layer
-            {
-                ceiling_altitudes.push(altitude);
-            }
+            let candidate = match layer {
+                metar::CloudLayer::Broken(_, altitude)
+                | metar::CloudLayer::Overcast(_, altitude) => match altitude {
+                    Some(altitude) => thresholds.classify(*altitude),
+                    None => FlightRules::LowIfr,
+                },
+                _ => continue,
+            };
+
+            rules = Some(match rules {
+                Some(existing) => existing.worse(candidate),
+                None => candidate,
+            });
         }
 
-        match ceiling_altitudes.into_iter().min() {
-            Some(altitude) => {
-                if *altitude < 5 {
-                    FlightRules::LowIfr
-                } else if *altitude < 10 {
-                    FlightRules::Ifr
-                } else if *altitude <= 30 {
-                    FlightRules::MarginalVfr
-                } else {
-                    FlightRules::Vfr
-                }
-            }
-            None => FlightRules::Vfr,
-        }
+        rules.unwrap_or(FlightRules::Vfr)
     }
 }
 
-impl TryFrom<&metar::Metar<'_>> for FlightRules {
-    type Error = anyhow::Error;
+fn flight_rules_for_metar(m: &metar::Metar, thresholds: &CeilingThresholds) -> Result<FlightRules> {
+    let visibility = match &m.visibility {
+        metar::Data::Known(visibility) => visibility,
+        metar::Data::Unknown => return Err(format_err!("missing visibility")),
+    };
 
-    fn try_from(m: &metar::Metar) -> Result<Self, Self::Error> {
-        match (&m.visibility, &m.cloud_layers) {
-            (metar::Data::Known(visibility), cloud_layers) => {
-                let visibility_flight_rules: FlightRules = visibility.try_into()?;
-                let cloud_layers_flight_rules: FlightRules = cloud_layers.into();
+    let mut rules = FlightRules::try_from(visibility)?
+        .worse(FlightRules::from_cloud_layers(&m.cloud_layers, thresholds));
 
-                if visibility_flight_rules < cloud_layers_flight_rules {
-                    Ok(visibility_flight_rules)
-                } else {
-                    Ok(cloud_layers_flight_rules)
-                }
-            }
-            (metar::Data::Unknown, _) => {
-                return Err(format_err!("missing visibility"));
-            }
-        }
+    if let Some(vert_visibility) = &m.vert_visibility {
+        let vert_rules = match vert_visibility {
+            metar::VertVisibility::Distance(hundreds_ft) => thresholds.classify(*hundreds_ft),
+            metar::VertVisibility::ReducedByUnknownAmount => FlightRules::LowIfr,
+        };
+        rules = rules.worse(vert_rules);
     }
+
+    Ok(rules)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 enum FlightRulesColor {
     Purple,
     Red,
     Blue,
     Green,
+    Stale,
 }
 
 impl From<FlightRules> for FlightRulesColor {
@@ -170,23 +235,134 @@ impl fmt::Display for FlightRulesColor {
                 FlightRulesColor::Red => "r",
                 FlightRulesColor::Blue => "b",
                 FlightRulesColor::Green => "g",
+                FlightRulesColor::Stale => "s",
             }
         )
     }
 }
 
-struct ColorAndPort {
+fn metar_observation_age(
+    time: &metar::Time,
+    now: chrono::DateTime<Utc>,
+) -> Result<chrono::Duration> {
+    let mut year = now.year();
+    let mut month = now.month();
+    // METAR only carries a day-of-month, so a report dated later than today
+    // must belong to the previous month.
+    if u32::from(time.date) > now.day() {
+        if month == 1 {
+            month = 12;
+            year -= 1;
+        } else {
+            month -= 1;
+        }
+    }
+
+    let observed = Utc
+        .with_ymd_and_hms(
+            year,
+            month,
+            u32::from(time.date),
+            u32::from(time.hour),
+            u32::from(time.minute),
+            0,
+        )
+        .single()
+        .ok_or_else(|| format_err!("invalid METAR observation time"))?;
+
+    Ok(now - observed)
+}
+
+impl FlightRulesColor {
+    fn rgb(&self) -> [u8; 3] {
+        match self {
+            FlightRulesColor::Purple => [128, 0, 128],
+            FlightRulesColor::Red => [255, 0, 0],
+            FlightRulesColor::Blue => [0, 0, 255],
+            FlightRulesColor::Green => [0, 128, 0],
+            FlightRulesColor::Stale => [128, 128, 128],
+        }
+    }
+}
+
+struct Observation {
     color: FlightRulesColor,
+    raw: String,
+    rules: Option<FlightRules>,
+    age_min: i64,
+}
+
+const BACKOFF_BASE_S: u64 = 30;
+const BACKOFF_EXP_CAP: u32 = 3;
+
+fn backoff_duration(errors: u32) -> Duration {
+    Duration::from_secs(BACKOFF_BASE_S * 2u64.pow(min(errors, BACKOFF_EXP_CAP)))
+}
+
+struct StationState {
     port: u16,
+    last_good: Option<FlightRulesColor>,
+    error_count: u32,
+    next_fetch: Instant,
+    raw_metar: Option<String>,
+    rules: Option<FlightRules>,
+    age_min: Option<i64>,
+}
+
+impl StationState {
+    fn new(port: u16) -> Self {
+        StationState {
+            port,
+            last_good: None,
+            error_count: 0,
+            next_fetch: Instant::now(),
+            raw_metar: None,
+            rules: None,
+            age_min: None,
+        }
+    }
 }
 
-async fn flight_rules_color_for_airport(airport: &str, port: u16) -> Result<ColorAndPort> {
-    let res = reqwest::get(&format!(
-        "https://tgftp.nws.noaa.gov/data/observations/metar/stations/{}.TXT",
-        airport
-    ))
-    .await
-    .with_context(|| format_err!("failed to fetch METAR for {}", airport))?;
+type SharedStates = Arc<Mutex<HashMap<String, StationState>>>;
+
+fn build_client(
+    proxy_url: Option<&str>,
+    proxy_username: Option<&str>,
+    proxy_password: Option<&str>,
+) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+
+    if let Some(url) = proxy_url {
+        let mut proxy =
+            reqwest::Proxy::all(url).with_context(|| format_err!("invalid proxy URL {}", url))?;
+        if let Some(username) = proxy_username {
+            proxy = proxy.basic_auth(username, proxy_password.unwrap_or(""));
+        }
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().context("failed to build HTTP client")
+}
+
+#[tracing::instrument(
+    skip(client, max_age_min, thresholds),
+    fields(latency_ms = tracing::field::Empty, category = tracing::field::Empty),
+)]
+async fn flight_rules_color_for_airport(
+    client: &reqwest::Client,
+    airport: &str,
+    max_age_min: i64,
+    thresholds: CeilingThresholds,
+) -> Result<Observation> {
+    let started = Instant::now();
+    let res = client
+        .get(&format!(
+            "https://tgftp.nws.noaa.gov/data/observations/metar/stations/{}.TXT",
+            airport
+        ))
+        .send()
+        .await
+        .with_context(|| format_err!("failed to fetch METAR for {}", airport))?;
 
     let body = res.text().await.with_context(|| {
         format_err!(
@@ -198,17 +374,39 @@ async fn flight_rules_color_for_airport(airport: &str, port: u16) -> Result<Colo
     let mut lines = body.lines();
     lines.next();
 
-    let r = metar::Metar::parse(
-        &lines
-            .next()
-            .ok_or_else(|| format_err!("missing METAR line for {}", airport))?,
-    )
-    .map_err(|e| format_err!("failed to parse METAR for {}: {}", airport, e))?;
+    let raw = lines
+        .next()
+        .ok_or_else(|| format_err!("missing METAR line for {}", airport))?
+        .to_string();
+
+    let r = metar::Metar::parse(&raw)
+        .map_err(|e| format_err!("failed to parse METAR for {}: {}", airport, e))?;
+
+    let age = metar_observation_age(&r.time, Utc::now())
+        .with_context(|| format_err!("failed to compute METAR age for {}", airport))?;
+    let age_min = age.num_minutes();
+
+    let span = tracing::Span::current();
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+
+    if age_min > max_age_min {
+        span.record("category", "stale");
+        debug!(
+            "{} report is stale ({} min old, max {})",
+            airport, age_min, max_age_min,
+        );
+        return Ok(Observation {
+            color: FlightRulesColor::Stale,
+            raw,
+            rules: None,
+            age_min,
+        });
+    }
 
-    let rules: FlightRules = (&r)
-        .try_into()
+    let rules = flight_rules_for_metar(&r, &thresholds)
         .with_context(|| format_err!("failed to parse METAR into flight rules for {}", airport))?;
 
+    span.record("category", tracing::field::debug(&rules));
     debug!(
         "{} is {:?} ({})",
         airport,
@@ -216,43 +414,218 @@ async fn flight_rules_color_for_airport(airport: &str, port: u16) -> Result<Colo
         FlightRulesColor::from(rules.clone()),
     );
 
-    Ok(ColorAndPort {
-        color: rules.into(),
-        port,
+    Ok(Observation {
+        color: rules.clone().into(),
+        raw,
+        rules: Some(rules),
+        age_min,
     })
 }
 
-async fn set_colors(
-    serial_port: &str,
+struct PollConfig {
+    serial_port: String,
     baud_rate: u32,
     serial_port_timeout: Duration,
-    port_map: &HashMap<&str, u16>,
+    refetch_interval: Duration,
+    max_age_min: i64,
+    thresholds: CeilingThresholds,
+}
+
+async fn set_colors(
+    config: &PollConfig,
+    client: &reqwest::Client,
+    states: &SharedStates,
 ) -> Result<()> {
+    let now = Instant::now();
+
+    // Only issue requests for stations whose next fetch time (re-fetch
+    // interval after a success, backoff window after a failure) has elapsed;
+    // the rest keep being driven from their cached last-good color below.
+    let due: Vec<(String, u16)> = {
+        let states = states.lock().unwrap();
+        states
+            .iter()
+            .filter(|(_, state)| now >= state.next_fetch)
+            .map(|(airport, state)| (airport.clone(), state.port))
+            .collect()
+    };
+
     let mut futures = Vec::new();
-    for (airport, port) in port_map {
-        futures.push(flight_rules_color_for_airport(airport, *port));
+    for (airport, port) in due {
+        let client = client.clone();
+        let span = info_span!("station", icao = %airport, led = port);
+        futures.push(
+            async move {
+                (
+                    airport.clone(),
+                    flight_rules_color_for_airport(
+                        &client,
+                        &airport,
+                        config.max_age_min,
+                        config.thresholds,
+                    )
+                    .await,
+                )
+            }
+            .instrument(span),
+        );
+    }
+
+    let results = join_all(futures).await;
+
+    {
+        let mut states = states.lock().unwrap();
+        for (airport, result) in results {
+            let state = match states.get_mut(&airport) {
+                Some(state) => state,
+                None => continue,
+            };
+
+            match result {
+                Ok(observation) => {
+                    state.last_good = Some(observation.color);
+                    state.raw_metar = Some(observation.raw);
+                    state.rules = observation.rules;
+                    state.age_min = Some(observation.age_min);
+                    state.error_count = 0;
+                    state.next_fetch = Instant::now() + config.refetch_interval;
+                }
+                Err(e) => {
+                    state.error_count += 1;
+                    let backoff = backoff_duration(state.error_count);
+                    state.next_fetch = Instant::now() + backoff;
+                    error!(
+                        "failed to fetch flight rules for {} (retry {} in {}s): {:?}",
+                        airport,
+                        state.error_count,
+                        backoff.as_secs(),
+                        e,
+                    );
+                }
+            }
+        }
     }
 
-    let mut port = serialport::new(serial_port, baud_rate)
-        .timeout(serial_port_timeout)
+    let writes: Vec<(String, u16, FlightRulesColor)> = {
+        let states = states.lock().unwrap();
+        states
+            .iter()
+            .filter_map(|(airport, state)| {
+                state
+                    .last_good
+                    .clone()
+                    .map(|color| (airport.clone(), state.port, color))
+            })
+            .sorted_by_key(|(_, port, _)| *port)
+            .collect()
+    };
+
+    let mut port = serialport::new(&config.serial_port, config.baud_rate)
+        .timeout(config.serial_port_timeout)
         .open()
         .context("failed to open serial device")?;
 
-    for result in join_all(futures).await {
-        let color_and_port = result.context("failed to fetch flight rules")?;
-
-        port.write(&format!("{}{}", color_and_port.port, color_and_port.color).as_bytes())
-            .context("failed to write flight rules to microcontroller")?;
+    for (airport, led, color) in writes {
+        let span = info_span!(
+            "serial_write",
+            icao = %airport,
+            led,
+            color = %color,
+            latency_ms = tracing::field::Empty
+        );
+        let _entered = span.enter();
+        let started = Instant::now();
+        let result = port
+            .write(&format!("{}{}", led, color).as_bytes())
+            .context("failed to write flight rules to microcontroller");
+        span.record("latency_ms", started.elapsed().as_millis() as u64);
+        result?;
     }
 
     Ok(())
 }
 
+fn render_status_png(states: &HashMap<String, StationState>) -> Result<Vec<u8>> {
+    use image::{ImageOutputFormat, Rgb, RgbImage};
+
+    const SWATCH: u32 = 40;
+
+    let ordered: Vec<&StationState> = states.values().sorted_by_key(|s| s.port).collect();
+    let width = SWATCH * u32::try_from(ordered.len().max(1)).unwrap_or(1);
+    let mut img = RgbImage::new(width, SWATCH);
+
+    for (i, state) in ordered.iter().enumerate() {
+        let rgb = state.last_good.as_ref().map_or([0, 0, 0], |c| c.rgb());
+        let x0 = u32::try_from(i).unwrap_or(0) * SWATCH;
+        for dx in 0..SWATCH {
+            for dy in 0..SWATCH {
+                img.put_pixel(x0 + dx, dy, Rgb(rgb));
+            }
+        }
+    }
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut buf, ImageOutputFormat::Png)
+        .context("failed to encode status PNG")?;
+    Ok(buf.into_inner())
+}
+
+async fn run_status_server(addr: SocketAddr, states: SharedStates) {
+    use warp::Filter;
+
+    let status_states = states.clone();
+    let status = warp::path("status").and(warp::get()).map(move || {
+        let states = status_states.lock().unwrap();
+        let entries: Vec<_> = states
+            .iter()
+            .sorted_by_key(|(_, s)| s.port)
+            .map(|(icao, s)| {
+                serde_json::json!({
+                    "icao": icao,
+                    "led": s.port,
+                    "color": s.last_good.as_ref().map(|c| c.to_string()),
+                    "flight_rules": s.rules.as_ref().map(|r| format!("{:?}", r)),
+                    "raw_metar": s.raw_metar,
+                    "observation_age_min": s.age_min,
+                    "error_count": s.error_count,
+                })
+            })
+            .collect();
+        warp::reply::json(&entries)
+    });
+
+    let png_states = states.clone();
+    let map = warp::path("map.png").and(warp::get()).map(move || {
+        match render_status_png(&png_states.lock().unwrap()) {
+            Ok(png) => warp::http::Response::builder()
+                .header("content-type", "image/png")
+                .body(png)
+                .unwrap(),
+            Err(e) => {
+                error!("failed to render status PNG: {:?}", e);
+                warp::http::Response::builder()
+                    .status(500)
+                    .body(Vec::new())
+                    .unwrap()
+            }
+        }
+    });
+
+    warp::serve(status.or(map)).run(addr).await;
+}
+
 #[tokio::main]
 async fn main() -> ! {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
-
     let args = Args::from_args();
+
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn"));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match args.log_format.as_str() {
+        "json" => subscriber.json().init(),
+        _ => subscriber.init(),
+    }
+
     let serial_port_timeout = Duration::from_millis(args.serial_timeout_ms);
 
     let port_map: HashMap<&str, u16> = hashmap! {
@@ -267,23 +640,195 @@ async fn main() -> ! {
         info!("{} on LED{}", airport, port);
     }
 
-    let mut timer = tokio::time::interval(Duration::from_secs(args.refresh_interval_s));
+    let states: SharedStates = Arc::new(Mutex::new(
+        port_map
+            .iter()
+            .map(|(airport, port)| (airport.to_string(), StationState::new(*port)))
+            .collect(),
+    ));
+
+    if let Some(addr) = args.http_addr {
+        info!("serving status dashboard on http://{}", addr);
+        let server_states = states.clone();
+        tokio::spawn(async move {
+            run_status_server(addr, server_states).await;
+        });
+    }
+
+    let client = match build_client(
+        args.proxy_url.as_deref(),
+        args.proxy_username.as_deref(),
+        args.proxy_password.as_deref(),
+    ) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("failed to build HTTP client: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let poll_config = PollConfig {
+        serial_port: args.serial_port.clone(),
+        baud_rate: args.baud_rate,
+        serial_port_timeout,
+        refetch_interval: Duration::from_secs(args.station_refetch_interval_s),
+        max_age_min: args.metar_max_age_min,
+        thresholds: CeilingThresholds {
+            lifr: args.ceiling_lifr_hundreds_ft,
+            ifr: args.ceiling_ifr_hundreds_ft,
+            mvfr: args.ceiling_mvfr_hundreds_ft,
+        },
+    };
+
+    let mut timer = tokio::time::interval(Duration::from_secs(args.search_interval_s));
     timer.tick().await;
 
     loop {
         info!("Querying METARs and setting colors");
 
-        if let Err(e) = set_colors(
-            &args.serial_port,
-            args.baud_rate,
-            serial_port_timeout.clone(),
-            &port_map,
-        )
-        .await
-        {
+        if let Err(e) = set_colors(&poll_config, &client, &states).await {
             error!("failed to set colors: {:?}", e);
         }
 
         timer.tick().await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    #[test]
+    fn observation_age_same_day() {
+        let now = utc(2026, 7, 29, 12, 0);
+        let time = metar::Time {
+            date: 29,
+            hour: 11,
+            minute: 30,
+        };
+
+        let age = metar_observation_age(&time, now).unwrap();
+        assert_eq!(age.num_minutes(), 30);
+    }
+
+    #[test]
+    fn observation_age_rolls_back_month() {
+        let now = utc(2026, 3, 1, 1, 0);
+        let time = metar::Time {
+            date: 28,
+            hour: 23,
+            minute: 0,
+        };
+
+        let age = metar_observation_age(&time, now).unwrap();
+        assert_eq!(age.num_minutes(), 120);
+    }
+
+    #[test]
+    fn observation_age_rolls_back_year_over_january() {
+        let now = utc(2026, 1, 1, 0, 30);
+        let time = metar::Time {
+            date: 31,
+            hour: 23,
+            minute: 0,
+        };
+
+        let age = metar_observation_age(&time, now).unwrap();
+        assert_eq!(age.num_minutes(), 90);
+    }
+
+    #[test]
+    fn observation_age_rejects_day_that_does_not_exist_in_rolled_back_month() {
+        let now = utc(2026, 3, 5, 0, 0);
+        let time = metar::Time {
+            date: 30,
+            hour: 0,
+            minute: 0,
+        };
+
+        assert!(metar_observation_age(&time, now).is_err());
+    }
+
+    const DEFAULT_THRESHOLDS: CeilingThresholds = CeilingThresholds {
+        lifr: 5,
+        ifr: 10,
+        mvfr: 30,
+    };
+
+    #[test]
+    fn classify_boundaries() {
+        assert_eq!(DEFAULT_THRESHOLDS.classify(4), FlightRules::LowIfr);
+        assert_eq!(DEFAULT_THRESHOLDS.classify(9), FlightRules::Ifr);
+        assert_eq!(DEFAULT_THRESHOLDS.classify(30), FlightRules::MarginalVfr);
+        assert_eq!(DEFAULT_THRESHOLDS.classify(31), FlightRules::Vfr);
+    }
+
+    #[test]
+    fn classify_with_misconfigured_thresholds_does_not_panic() {
+        let thresholds = CeilingThresholds {
+            lifr: 30,
+            ifr: 10,
+            mvfr: 5,
+        };
+
+        // With an inverted ordering the `ifr` and `mvfr` branches never match,
+        // since the `lifr` branch is checked first and already takes every
+        // height below `lifr`; this documents the current (degenerate) behavior rather than asserting
+        // it is the "right" answer for a misconfigured `Args`.
+        assert_eq!(thresholds.classify(1), FlightRules::LowIfr);
+        assert_eq!(thresholds.classify(20), FlightRules::LowIfr);
+        assert_eq!(thresholds.classify(40), FlightRules::Vfr);
+    }
+
+    #[test]
+    fn cloud_layers_unspecified_altitude_is_treated_as_low_ifr() {
+        let layers = vec![metar::CloudLayer::Broken(metar::CloudType::Normal, None)];
+        assert_eq!(
+            FlightRules::from_cloud_layers(&layers, &DEFAULT_THRESHOLDS),
+            FlightRules::LowIfr
+        );
+    }
+
+    #[test]
+    fn cloud_layers_known_altitude_uses_thresholds() {
+        let layers = vec![metar::CloudLayer::Overcast(
+            metar::CloudType::Normal,
+            Some(8),
+        )];
+        assert_eq!(
+            FlightRules::from_cloud_layers(&layers, &DEFAULT_THRESHOLDS),
+            FlightRules::Ifr
+        );
+    }
+
+    #[test]
+    fn cloud_layers_with_no_ceiling_is_vfr() {
+        let layers = vec![metar::CloudLayer::Few(metar::CloudType::Normal, Some(2))];
+        assert_eq!(
+            FlightRules::from_cloud_layers(&layers, &DEFAULT_THRESHOLDS),
+            FlightRules::Vfr
+        );
+    }
+
+    #[test]
+    fn vertical_visibility_with_known_height_uses_thresholds() {
+        let raw = "KTST 291200Z 00000KT 10SM VV004 20/15 A3000";
+        let metar = metar::Metar::parse(raw).unwrap();
+        let rules = flight_rules_for_metar(&metar, &DEFAULT_THRESHOLDS).unwrap();
+        assert_eq!(rules, FlightRules::LowIfr);
+    }
+
+    #[test]
+    fn vertical_visibility_reduced_by_unknown_amount_is_low_ifr() {
+        let raw = "KTST 291200Z 00000KT 10SM VV/// 20/15 A3000";
+        let metar = metar::Metar::parse(raw).unwrap();
+        let rules = flight_rules_for_metar(&metar, &DEFAULT_THRESHOLDS).unwrap();
+        assert_eq!(rules, FlightRules::LowIfr);
+    }
+}